@@ -0,0 +1,32 @@
+use getopts::{Matches, Options};
+
+/// Adds the `-r/--root-path` and `-d/--db-path` options shared by every
+/// subcommand that needs to talk to ALPM.
+pub fn add_common_opts(opts: &mut Options) {
+    opts.optflag("h", "help", "print usage");
+    opts.optopt("r", "root-path", "the absolute path to the system root filesystem (default = \"/\")", "PATH");
+    opts.optopt("d", "db-path", "the absolute path to the ALPM database (default = \"/var/lib/pacman\")", "PATH");
+}
+
+pub fn root_path(matches: &Matches) -> String {
+    matches.opt_str("r").unwrap_or(String::from("/"))
+}
+
+pub fn database_path(matches: &Matches) -> String {
+    matches.opt_str("d").unwrap_or(String::from("/var/lib/pacman"))
+}
+
+pub fn show_usage(launch_name: &str, command: &str, opts: &Options) {
+    let brief = format!("Usage:\n{} {} [options]", launch_name, command);
+    eprintln!("{}", opts.usage(&brief));
+}
+
+/// Adds the `--db` option shared by every subcommand that can store or load
+/// snapshots through the SQLite backend instead of TOML/JSON files.
+pub fn add_db_opt(opts: &mut Options) {
+    opts.optopt("", "db", "use a SQLite database for storage instead of a file; arguments that take a filename take a snapshot name instead", "PATH");
+}
+
+pub fn db_path(matches: &Matches) -> Option<String> {
+    matches.opt_str("db")
+}
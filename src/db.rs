@@ -0,0 +1,147 @@
+use std::error::Error;
+
+use rusqlite::{params, Connection};
+
+use crate::snapshot::{BiscuitSnapshot, InstallReason, Origin, PackageInfo};
+
+fn reason_to_str(reason: InstallReason) -> &'static str {
+    match reason {
+        InstallReason::Explicit => "explicit",
+        InstallReason::Dependency => "dependency",
+    }
+}
+
+fn reason_from_str(s: &str) -> InstallReason {
+    match s {
+        "explicit" => InstallReason::Explicit,
+        _ => InstallReason::Dependency,
+    }
+}
+
+fn open(db_path: &str) -> Result<Connection, Box<dyn Error>> {
+    let conn = Connection::open(db_path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            datetime TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS packages (
+            snapshot_id INTEGER NOT NULL REFERENCES snapshots(id),
+            name TEXT NOT NULL,
+            version TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            repo TEXT
+        );",
+    )?;
+    Ok(conn)
+}
+
+/// Stores `snapshot` as a new row in the `snapshots` table, together with its
+/// packages in the `packages` table. Snapshots are never overwritten by name,
+/// so saving the same name twice keeps both as separate history entries.
+pub fn save_to_db(snapshot: &BiscuitSnapshot, db_path: &str) -> Result<(), Box<dyn Error>> {
+    let mut conn = open(db_path)?;
+    let tx = conn.transaction()?;
+
+    tx.execute(
+        "INSERT INTO snapshots (name, datetime) VALUES (?1, ?2)",
+        params![snapshot.name, snapshot.datetime.to_rfc3339()],
+    )?;
+    let snapshot_id = tx.last_insert_rowid();
+
+    for pkg in &snapshot.package_infos {
+        tx.execute(
+            "INSERT INTO packages (snapshot_id, name, version, reason, repo) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                snapshot_id,
+                pkg.name,
+                pkg.version,
+                reason_to_str(pkg.reason),
+                pkg.origin.as_repo_name(),
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Loads the most recently saved snapshot with the given `name`.
+pub fn load_from_db(db_path: &str, name: &str) -> Result<BiscuitSnapshot, Box<dyn Error>> {
+    let conn = open(db_path)?;
+    let (id, datetime): (i64, String) = conn.query_row(
+        "SELECT id, datetime FROM snapshots WHERE name = ?1 ORDER BY id DESC LIMIT 1",
+        params![name],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut stmt =
+        conn.prepare("SELECT name, version, reason, repo FROM packages WHERE snapshot_id = ?1")?;
+    let package_infos = stmt
+        .query_map(params![id], |row| {
+            let repo: Option<String> = row.get(3)?;
+            let reason: String = row.get(2)?;
+            Ok(PackageInfo {
+                name: row.get(0)?,
+                version: row.get(1)?,
+                reason: reason_from_str(&reason),
+                origin: Origin::from_repo_name(repo),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(BiscuitSnapshot {
+        name: name.to_string(),
+        datetime: datetime.parse()?,
+        package_infos,
+    })
+}
+
+pub struct SnapshotSummary {
+    pub id: i64,
+    pub name: String,
+    pub datetime: String,
+}
+
+/// Enumerates every snapshot stored in the database, oldest first.
+pub fn list_snapshots(db_path: &str) -> Result<Vec<SnapshotSummary>, Box<dyn Error>> {
+    let conn = open(db_path)?;
+    let mut stmt = conn.prepare("SELECT id, name, datetime FROM snapshots ORDER BY id")?;
+    let summaries = stmt
+        .query_map([], |row| {
+            Ok(SnapshotSummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                datetime: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(summaries)
+}
+
+/// Finds which stored snapshots contained a package named `name`, optionally
+/// narrowed to a specific `version`.
+pub fn find_snapshots_with_package(
+    db_path: &str,
+    name: &str,
+    version: Option<&str>,
+) -> Result<Vec<SnapshotSummary>, Box<dyn Error>> {
+    let conn = open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT s.id, s.name, s.datetime FROM snapshots s
+         JOIN packages p ON p.snapshot_id = s.id
+         WHERE p.name = ?1 AND (?2 IS NULL OR p.version = ?2)
+         ORDER BY s.id",
+    )?;
+    let summaries = stmt
+        .query_map(params![name, version], |row| {
+            Ok(SnapshotSummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                datetime: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(summaries)
+}
@@ -0,0 +1,154 @@
+use std::error::Error;
+
+use getopts::Options;
+use serde_derive::Serialize;
+
+use crate::cli;
+use crate::db;
+use crate::snapshot::{self, BiscuitSnapshot};
+
+#[derive(Serialize)]
+pub struct VersionChange {
+    pub name: String,
+    pub old_version: String,
+    pub new_version: String,
+}
+
+#[derive(Serialize)]
+pub struct SnapshotDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<VersionChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Joins the `package_infos` of `before` and `after` on package name to work
+/// out what was added, removed, or changed version between the two.
+pub fn compute_diff(before: &BiscuitSnapshot, after: &BiscuitSnapshot) -> SnapshotDiff {
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+
+    for after_pkg in &after.package_infos {
+        match before
+            .package_infos
+            .iter()
+            .find(|p| p.name == after_pkg.name)
+        {
+            Some(before_pkg) => {
+                if before_pkg.version != after_pkg.version {
+                    changed.push(VersionChange {
+                        name: after_pkg.name.clone(),
+                        old_version: before_pkg.version.clone(),
+                        new_version: after_pkg.version.clone(),
+                    });
+                }
+            }
+            None => added.push(after_pkg.name.clone()),
+        }
+    }
+
+    let removed = before
+        .package_infos
+        .iter()
+        .filter(|p| !after.package_infos.iter().any(|q| q.name == p.name))
+        .map(|p| p.name.clone())
+        .collect();
+
+    SnapshotDiff {
+        added,
+        removed,
+        changed,
+    }
+}
+
+pub fn print_diff_human(diff: &SnapshotDiff) {
+    if diff.is_empty() {
+        println!("No differences found.");
+        return;
+    }
+
+    if !diff.added.is_empty() {
+        println!("Added ({}):", diff.added.len());
+        for name in &diff.added {
+            println!("  + {}", name);
+        }
+    }
+
+    if !diff.removed.is_empty() {
+        println!("Removed ({}):", diff.removed.len());
+        for name in &diff.removed {
+            println!("  - {}", name);
+        }
+    }
+
+    if !diff.changed.is_empty() {
+        println!("Changed ({}):", diff.changed.len());
+        for change in &diff.changed {
+            println!("  * {}: {} -> {}", change.name, change.old_version, change.new_version);
+        }
+    }
+}
+
+pub fn print_diff_json(diff: &SnapshotDiff) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(diff)?;
+    println!("{}", json);
+    Ok(())
+}
+
+pub fn run(launch_name: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut opts = Options::new();
+    cli::add_common_opts(&mut opts);
+    cli::add_db_opt(&mut opts);
+    opts.optopt("a", "before", "[Required] the snapshot file (or, with --db, snapshot name) to compare from", "FILE");
+    opts.optopt("b", "after", "the snapshot file (or, with --db, snapshot name) to compare to (default = the current system)", "FILE");
+    opts.optflag("", "json", "print the diff as JSON instead of human-readable text");
+
+    let matches = opts.parse(args).map_err(|f| {
+        cli::show_usage(launch_name, "diff", &opts);
+        f
+    })?;
+
+    if matches.opt_present("h") {
+        cli::show_usage(launch_name, "diff", &opts);
+        return Ok(());
+    }
+
+    if !matches.opt_present("a") {
+        cli::show_usage(launch_name, "diff", &opts);
+        return Err("Missing required argument: before".into());
+    }
+
+    let before_source = matches.opt_str("a").unwrap();
+    let before = match cli::db_path(&matches) {
+        Some(ref db_path) => db::load_from_db(db_path, &before_source)?,
+        None => BiscuitSnapshot::load_from_file(&before_source)?,
+    };
+
+    let after = match matches.opt_str("b") {
+        Some(after_source) => match cli::db_path(&matches) {
+            Some(ref db_path) => db::load_from_db(db_path, &after_source)?,
+            None => BiscuitSnapshot::load_from_file(&after_source)?,
+        },
+        None => {
+            let root_path = cli::root_path(&matches);
+            let database_path = cli::database_path(&matches);
+            let mut live = BiscuitSnapshot::create_with_name("current-system");
+            snapshot::write_to_snapshot(&mut live, &root_path, &database_path)?;
+            live
+        }
+    };
+
+    let diff = compute_diff(&before, &after);
+    if matches.opt_present("json") {
+        print_diff_json(&diff)?;
+    } else {
+        print_diff_human(&diff);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::error::Error;
+
+use getopts::Options;
+
+use crate::cli;
+use crate::db;
+use crate::db::SnapshotSummary;
+
+fn print_summaries(summaries: &[SnapshotSummary]) {
+    if summaries.is_empty() {
+        println!("No snapshots found.");
+        return;
+    }
+
+    for summary in summaries {
+        println!("{}\t{}\t{}", summary.id, summary.name, summary.datetime);
+    }
+}
+
+pub fn run(launch_name: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print usage");
+    opts.optopt("", "db", "[Required] the SQLite database to query", "PATH");
+    opts.optopt("", "package", "only list snapshots that contain this package", "NAME");
+    opts.optopt("", "package-version", "narrow --package to a specific version", "VERSION");
+
+    let matches = opts.parse(args).map_err(|f| {
+        cli::show_usage(launch_name, "list", &opts);
+        f
+    })?;
+
+    if matches.opt_present("h") {
+        cli::show_usage(launch_name, "list", &opts);
+        return Ok(());
+    }
+
+    if !matches.opt_present("db") {
+        cli::show_usage(launch_name, "list", &opts);
+        return Err("Missing required argument: db".into());
+    }
+
+    let db_path = matches.opt_str("db").unwrap();
+
+    let summaries = match matches.opt_str("package") {
+        Some(package) => {
+            let version = matches.opt_str("package-version");
+            db::find_snapshots_with_package(&db_path, &package, version.as_deref())?
+        }
+        None => db::list_snapshots(&db_path)?,
+    };
+
+    print_summaries(&summaries);
+
+    Ok(())
+}
@@ -1,121 +1,55 @@
 use std::env;
-use std::error::Error;
 use std::process::exit;
-use std::fs::OpenOptions;
 
-use getopts::Options;
-
-use serde_derive::Serialize;
-
-use chrono::{DateTime, Utc};
-use std::io::Write;
-
-#[derive(Serialize)]
-struct PackageInfo {
-    name: String,
-    version: String,
-}
-
-#[derive(Serialize)]
-struct BiscuitSnapshot {
-    name: String,
-    datetime: DateTime<Utc>,
-    package_infos: Vec<PackageInfo>,
-}
-
-impl BiscuitSnapshot {
-    pub fn create_with_name(name: &str) -> BiscuitSnapshot {
-        BiscuitSnapshot {
-            name: name.to_string(),
-            datetime: Utc::now(),
-            package_infos: Vec::new(),
-        }
-    }
-
-    pub fn add_package_info(&mut self, name: &str, version: &str) {
-        self.package_infos.push(PackageInfo{
-            name: name.to_string(),
-            version: version.to_string(),
-        });
-    }
-
-    pub fn save_to_file(&self, filename: &str) -> Result<(), Box<dyn Error>> {
-        let toml = toml::to_string(&self)?;
-        let mut out_file = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(filename)?;
-        out_file.write_all(toml.as_bytes()).map_err(|e| e.into())
-    }
-}
-
-fn write_to_snapshot(snapshot: &mut BiscuitSnapshot, root_path: &str, database_path: &str) -> Result<(), Box<dyn Error>> {
-    let handle = alpm_rs::initialize(root_path, database_path)?;
-    let db = handle.local_db();
-    let packages = db.pkgcache();
-
-    for p in packages {
-        snapshot.add_package_info(p.name(), p.version());
-    }
-
-    Ok(())
-}
-
-fn show_usage(launch_name: &str, opts: Options) {
-    let brief = format!("Usage:\n{} [-h]", launch_name);
-    eprintln!("{}", opts.usage(&brief));
+mod cli;
+mod db;
+mod diff;
+mod list;
+mod restore;
+mod snapshot;
+
+fn show_top_level_usage(launch_name: &str) {
+    eprintln!("Usage:\n{} <command> [options]\n", launch_name);
+    eprintln!("Commands:");
+    eprintln!("  snapshot   Record the currently installed packages to a file or SQLite database");
+    eprintln!("  restore    Reinstall packages to match a saved snapshot");
+    eprintln!("  diff       Compare two snapshots, or a snapshot against the live system");
+    eprintln!("  list       Enumerate snapshots stored in a SQLite database");
+    eprintln!("\nRun '{} <command> -h' for command-specific options.", launch_name);
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-
     let launch_name = args[0].clone();
 
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print usage");
-    opts.optopt("n", "name", "[Required] the name of the snapshot", "NAME");
-    opts.optopt("o", "output", "the output filename (default = \"NAME.toml\")", "FILE");
-    opts.optopt("r", "root-path", "the absolute path to the system root filesystem (default = \"/\")", "PATH");
-    opts.optopt("d", "db-path", "the absolute path to the ALPM database (default = \"/var/lib/pacman\")", "PATH");
+    if args.len() < 2 {
+        eprintln!("Missing required argument: command");
+        show_top_level_usage(&launch_name);
+        exit(1);
+    }
 
-    let matches = match opts.parse(&args[1..]) {
-        Ok(m) => m,
-        Err(f) => {
-            eprintln!("Bad arguments: {}", f.to_string());
-            show_usage(&launch_name, opts);
-            exit(1);
-        }
-    };
+    let command = args[1].as_str();
+    let command_args = &args[2..];
 
-    if matches.opt_present("h") {
-        show_usage(&launch_name, opts);
+    if command == "-h" || command == "--help" {
+        show_top_level_usage(&launch_name);
         exit(0);
     }
 
-    if !matches.opt_present("n") {
-        eprintln!("Missing required argument: name");
-        show_usage(&launch_name, opts);
-        exit(1);
-    }
-
-    let name = matches.opt_str("n").unwrap();
-    let output_filename = matches.opt_str("o").unwrap_or(format!("{}.toml", name));
-    let root_path = matches.opt_str("r").unwrap_or(String::from("/"));
-    let database_path = matches.opt_str("d").unwrap_or(String::from("/var/lib/pacman"));
-    let mut snapshot = BiscuitSnapshot::create_with_name(&name);
-    match write_to_snapshot(&mut snapshot, &root_path, &database_path) {
-        Ok(_) => {
-            match snapshot.save_to_file(&output_filename) {
-                Ok(_) => exit(0),
-                Err(e) => {
-                    eprintln!("Something went wrong while saving the snapshot to the file: {}", e.to_string());
-                    exit(1);
-                }
-            }
-        },
-        Err(e) => {
-            eprintln!("Something went wrong while reading the ALPM database: {}", e.to_string());
+    let result = match command {
+        "snapshot" => snapshot::run(&launch_name, command_args),
+        "restore" => restore::run(&launch_name, command_args),
+        "diff" => diff::run(&launch_name, command_args),
+        "list" => list::run(&launch_name, command_args),
+        other => {
+            eprintln!("Unknown command: {}", other);
+            show_top_level_usage(&launch_name);
             exit(1);
         }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
     }
 }
@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::Command;
+
+use getopts::Options;
+
+use crate::cli;
+use crate::db;
+use crate::snapshot::{BiscuitSnapshot, InstallReason, Origin};
+
+/// The set of changes needed to make the live system match a snapshot.
+pub struct RestorePlan {
+    pub to_install: Vec<String>,
+    /// Foreign/AUR packages that biscuit cannot install itself; these need
+    /// an AUR helper to be built and installed.
+    pub to_build: Vec<String>,
+    pub to_remove: Vec<String>,
+}
+
+impl RestorePlan {
+    pub fn is_empty(&self) -> bool {
+        self.to_install.is_empty() && self.to_build.is_empty() && self.to_remove.is_empty()
+    }
+}
+
+/// Compares `snapshot` against the currently installed packages and works out
+/// which packages need to be installed to converge on it. Only explicitly
+/// installed packages are targeted for (re)installation, since dependency
+/// packages are pulled in automatically by pacman; packages whose origin is
+/// foreign/AUR are routed to `to_build` instead of `to_install`. When
+/// `remove_extra` is set, packages installed locally but absent from the
+/// snapshot entirely (including former dependencies) are scheduled for
+/// removal.
+pub fn compute_restore_plan(
+    snapshot: &BiscuitSnapshot,
+    root_path: &str,
+    database_path: &str,
+    remove_extra: bool,
+) -> Result<RestorePlan, Box<dyn Error>> {
+    let handle = alpm_rs::initialize(root_path, database_path)?;
+    let db = handle.local_db();
+    let installed: Vec<String> = db.pkgcache().iter().map(|p| p.name().to_string()).collect();
+
+    let all_wanted: Vec<String> = snapshot
+        .package_infos
+        .iter()
+        .map(|p| p.name.clone())
+        .collect();
+
+    let mut to_install = Vec::new();
+    let mut to_build = Vec::new();
+    for pkg in &snapshot.package_infos {
+        if pkg.reason != InstallReason::Explicit || installed.contains(&pkg.name) {
+            continue;
+        }
+        match &pkg.origin {
+            Origin::Repo(_) => to_install.push(pkg.name.clone()),
+            Origin::Foreign => to_build.push(pkg.name.clone()),
+        }
+    }
+
+    let to_remove = if remove_extra {
+        installed
+            .iter()
+            .filter(|name| !all_wanted.contains(name))
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(RestorePlan {
+        to_install,
+        to_build,
+        to_remove,
+    })
+}
+
+/// Prints the plan in a human-readable form so the user can review it before
+/// biscuit touches the system.
+pub fn print_plan(plan: &RestorePlan) {
+    if plan.is_empty() {
+        println!("The system already matches the snapshot. Nothing to do.");
+        return;
+    }
+
+    if !plan.to_install.is_empty() {
+        println!("Packages to install ({}):", plan.to_install.len());
+        for name in &plan.to_install {
+            println!("  + {}", name);
+        }
+    }
+
+    if !plan.to_build.is_empty() {
+        println!("Foreign packages to build via an AUR helper ({}):", plan.to_build.len());
+        for name in &plan.to_build {
+            println!("  ~ {}", name);
+        }
+    }
+
+    if !plan.to_remove.is_empty() {
+        println!("Packages to remove ({}):", plan.to_remove.len());
+        for name in &plan.to_remove {
+            println!("  - {}", name);
+        }
+    }
+}
+
+/// Whether the current process is already running as root, by reading the
+/// effective uid out of `/proc/self/status`.
+fn running_as_root() -> bool {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status.lines().find_map(|line| {
+                line.strip_prefix("Uid:")
+                    .and_then(|rest| rest.split_whitespace().nth(1))
+                    .and_then(|uid| uid.parse::<u32>().ok())
+            })
+        })
+        .map(|uid| uid == 0)
+        .unwrap_or(false)
+}
+
+/// Builds a `pacman` invocation, going through `sudo` when biscuit is not
+/// already running as root so the command doesn't just fail with a
+/// permission error.
+fn pacman_command() -> Command {
+    if running_as_root() {
+        Command::new("pacman")
+    } else {
+        let mut cmd = Command::new("sudo");
+        cmd.arg("pacman");
+        cmd
+    }
+}
+
+/// Drives `pacman` (for repo packages) and an AUR helper (for foreign
+/// packages) to install and remove packages so the plan is carried out.
+pub fn execute_restore_plan(
+    plan: &RestorePlan,
+    noconfirm: bool,
+    aur_helper: &str,
+) -> Result<(), Box<dyn Error>> {
+    if !plan.to_install.is_empty() {
+        let mut cmd = pacman_command();
+        cmd.arg("-S").args(&plan.to_install);
+        if noconfirm {
+            cmd.arg("--noconfirm");
+        }
+        let status = cmd.status()?;
+        if !status.success() {
+            return Err(format!("pacman exited with {}", status).into());
+        }
+    }
+
+    if !plan.to_build.is_empty() {
+        // AUR helpers run as the invoking user (makepkg refuses to run as
+        // root) and escalate to pacman themselves when installing, so this
+        // is intentionally not routed through `pacman_command`.
+        let mut cmd = Command::new(aur_helper);
+        cmd.arg("-S").args(&plan.to_build);
+        if noconfirm {
+            cmd.arg("--noconfirm");
+        }
+        let status = cmd.status().map_err(|e| -> Box<dyn Error> {
+            if e.kind() == ErrorKind::NotFound {
+                format!(
+                    "AUR helper '{}' was not found in PATH; install one (e.g. yay, paru), pass a different one with --aur-helper, or build these manually: {}",
+                    aur_helper,
+                    plan.to_build.join(", ")
+                )
+                .into()
+            } else {
+                e.into()
+            }
+        })?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", aur_helper, status).into());
+        }
+    }
+
+    if !plan.to_remove.is_empty() {
+        let mut cmd = pacman_command();
+        cmd.arg("-R").args(&plan.to_remove);
+        if noconfirm {
+            cmd.arg("--noconfirm");
+        }
+        let status = cmd.status()?;
+        if !status.success() {
+            return Err(format!("pacman exited with {}", status).into());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run(launch_name: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut opts = Options::new();
+    cli::add_common_opts(&mut opts);
+    cli::add_db_opt(&mut opts);
+    opts.optopt("f", "file", "[Required] the snapshot file (or, with --db, snapshot name) to restore from", "FILE");
+    opts.optflag("", "remove-extra", "also remove packages that are installed but not in the snapshot");
+    opts.optflag("", "noconfirm", "do not ask pacman or the AUR helper for confirmation");
+    opts.optopt("", "aur-helper", "the AUR helper to use for foreign packages (default = \"yay\")", "NAME");
+
+    let matches = opts.parse(args).map_err(|f| {
+        cli::show_usage(launch_name, "restore", &opts);
+        f
+    })?;
+
+    if matches.opt_present("h") {
+        cli::show_usage(launch_name, "restore", &opts);
+        return Ok(());
+    }
+
+    if !matches.opt_present("f") {
+        cli::show_usage(launch_name, "restore", &opts);
+        return Err("Missing required argument: file".into());
+    }
+
+    let source = matches.opt_str("f").unwrap();
+    let root_path = cli::root_path(&matches);
+    let database_path = cli::database_path(&matches);
+    let remove_extra = matches.opt_present("remove-extra");
+    let noconfirm = matches.opt_present("noconfirm");
+    let aur_helper = matches.opt_str("aur-helper").unwrap_or(String::from("yay"));
+
+    let snapshot = match cli::db_path(&matches) {
+        Some(db_path) => db::load_from_db(&db_path, &source)?,
+        None => BiscuitSnapshot::load_from_file(&source)?,
+    };
+    let plan = compute_restore_plan(&snapshot, &root_path, &database_path, remove_extra)?;
+
+    print_plan(&plan);
+    if plan.is_empty() {
+        return Ok(());
+    }
+
+    execute_restore_plan(&plan, noconfirm, &aur_helper)
+}
@@ -0,0 +1,349 @@
+use std::error::Error;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use getopts::Options;
+use serde_derive::{Deserialize, Serialize};
+
+use chrono::{DateTime, Utc};
+
+use crate::cli;
+use crate::db;
+
+/// Why a package is installed, as reported by ALPM.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InstallReason {
+    /// The user asked for this package directly.
+    Explicit,
+    /// The package was pulled in to satisfy another package's dependency.
+    Dependency,
+}
+
+/// Where a package came from, so a restore knows how to reinstall it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// Provided by the named sync database (e.g. "core", "extra").
+    Repo(String),
+    /// Not found in any sync database, i.e. a foreign/AUR package.
+    Foreign,
+}
+
+impl Origin {
+    pub(crate) fn as_repo_name(&self) -> Option<&str> {
+        match self {
+            Origin::Repo(name) => Some(name.as_str()),
+            Origin::Foreign => None,
+        }
+    }
+
+    pub(crate) fn from_repo_name(repo: Option<String>) -> Origin {
+        match repo {
+            Some(name) => Origin::Repo(name),
+            None => Origin::Foreign,
+        }
+    }
+}
+
+/// Serializes `Origin` as a plain nullable repo name (`None` = foreign)
+/// instead of the default externally-tagged representation, which older
+/// `toml` releases cannot encode for a data-carrying enum variant. This also
+/// keeps the file formats lined up with the `repo TEXT` column the SQLite
+/// backend already uses (see db.rs).
+mod origin_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Origin;
+
+    pub fn serialize<S>(origin: &Origin, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        origin.as_repo_name().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Origin, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repo = Option::<String>::deserialize(deserializer)?;
+        Ok(Origin::from_repo_name(repo))
+    }
+
+    /// TOML has no null, so a foreign package's `repo` key is simply absent
+    /// from a TOML snapshot; a missing key loads as `Origin::Foreign`.
+    pub fn foreign() -> Origin {
+        Origin::Foreign
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+    pub reason: InstallReason,
+    #[serde(rename = "repo", with = "origin_serde", default = "origin_serde::foreign")]
+    pub origin: Origin,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BiscuitSnapshot {
+    pub name: String,
+    pub datetime: DateTime<Utc>,
+    pub package_infos: Vec<PackageInfo>,
+}
+
+/// The on-disk serialization used for a snapshot file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Json => "json",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Format, Box<dyn Error>> {
+        match s {
+            "toml" => Ok(Format::Toml),
+            "json" => Ok(Format::Json),
+            other => Err(format!("Unknown format: {}", other).into()),
+        }
+    }
+}
+
+impl BiscuitSnapshot {
+    pub fn create_with_name(name: &str) -> BiscuitSnapshot {
+        BiscuitSnapshot {
+            name: name.to_string(),
+            datetime: Utc::now(),
+            package_infos: Vec::new(),
+        }
+    }
+
+    pub fn add_package_info(&mut self, name: &str, version: &str, reason: InstallReason, origin: Origin) {
+        self.package_infos.push(PackageInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            reason,
+            origin,
+        });
+    }
+
+    pub fn save_to_file(&self, filename: &str, format: Format) -> Result<(), Box<dyn Error>> {
+        let serialized = match format {
+            Format::Toml => toml::to_string(&self)?,
+            Format::Json => serde_json::to_string_pretty(&self)?,
+        };
+        let mut out_file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(filename)?;
+        out_file.write_all(serialized.as_bytes()).map_err(|e| e.into())
+    }
+
+    pub fn load_from_file(filename: &str) -> Result<BiscuitSnapshot, Box<dyn Error>> {
+        let contents = fs::read_to_string(filename)?;
+        // The filename extension is only a hint chosen at write time (see
+        // `run`); a renamed or extension-less file must still load,
+        // so try both serializations instead of trusting the name.
+        if let Ok(snapshot) = toml::from_str(&contents) {
+            return Ok(snapshot);
+        }
+        if let Ok(snapshot) = serde_json::from_str(&contents) {
+            return Ok(snapshot);
+        }
+        Err(format!("{} is not a valid TOML or JSON snapshot", filename).into())
+    }
+}
+
+/// Reads the `[section]` headers out of `pacman.conf` under `root_path`,
+/// skipping `[options]`, to get the list of repos libalpm should know about.
+/// libalpm itself does not parse `pacman.conf` or auto-register repos, so
+/// this is the same step `pacman`/AUR helpers perform before touching sync
+/// databases.
+fn configured_repo_names(root_path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let conf_path = format!("{}/etc/pacman.conf", root_path.trim_end_matches('/'));
+    let contents = fs::read_to_string(&conf_path)?;
+    let names = contents
+        .lines()
+        .map(|line| line.trim())
+        .filter_map(|line| line.strip_prefix('[').and_then(|s| s.strip_suffix(']')))
+        .filter(|section| *section != "options")
+        .map(|section| section.to_string())
+        .collect();
+    Ok(names)
+}
+
+/// Registers every repo found in `pacman.conf` with `handle`, so its
+/// `syncdbs()` are actually populated. Without this, `handle.syncdbs()` is
+/// empty right after `alpm_rs::initialize` and every package looks foreign.
+fn register_configured_syncdbs(
+    handle: &mut alpm_rs::Handle,
+    root_path: &str,
+) -> Result<(), Box<dyn Error>> {
+    for name in configured_repo_names(root_path)? {
+        handle.register_syncdb(&name, alpm_rs::SigLevel::USE_DEFAULT)?;
+    }
+    Ok(())
+}
+
+/// Pure join logic behind origin resolution: given the package names each
+/// sync database carries, works out which repo (if any) provides `name`.
+/// Kept separate from the ALPM-facing code below so the join can be tested
+/// without a live handle.
+fn resolve_origin(syncdb_packages: &[(&str, Vec<&str>)], name: &str) -> Origin {
+    for (db_name, packages) in syncdb_packages {
+        if packages.contains(&name) {
+            return Origin::Repo((*db_name).to_string());
+        }
+    }
+    Origin::Foreign
+}
+
+/// Collects the package names each registered sync database carries, once,
+/// so resolving each local package's origin is a cache lookup rather than a
+/// fresh per-database scan of every synced package repeated per local
+/// package.
+fn collect_syncdb_packages(handle: &alpm_rs::Handle) -> Vec<(&str, Vec<&str>)> {
+    handle
+        .syncdbs()
+        .iter()
+        .map(|syncdb| {
+            let names = syncdb.pkgcache().iter().map(|p| p.name()).collect();
+            (syncdb.name(), names)
+        })
+        .collect()
+}
+
+pub fn write_to_snapshot(
+    snapshot: &mut BiscuitSnapshot,
+    root_path: &str,
+    database_path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut handle = alpm_rs::initialize(root_path, database_path)?;
+    register_configured_syncdbs(&mut handle, root_path)?;
+
+    let syncdb_packages = collect_syncdb_packages(&handle);
+
+    let db = handle.local_db();
+    let packages = db.pkgcache();
+
+    for p in packages {
+        let reason = match p.reason() {
+            alpm_rs::PackageReason::Explicit => InstallReason::Explicit,
+            alpm_rs::PackageReason::Depend => InstallReason::Dependency,
+        };
+        let origin = resolve_origin(&syncdb_packages, p.name());
+        snapshot.add_package_info(p.name(), p.version(), reason, origin);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_origin_finds_repo_package() {
+        let syncdbs = vec![("core", vec!["glibc", "pacman"]), ("extra", vec!["firefox"])];
+        assert_eq!(resolve_origin(&syncdbs, "pacman"), Origin::Repo("core".to_string()));
+    }
+
+    #[test]
+    fn resolve_origin_falls_back_to_foreign() {
+        let syncdbs = vec![("core", vec!["glibc"])];
+        assert_eq!(resolve_origin(&syncdbs, "yay"), Origin::Foreign);
+    }
+
+    fn sample_snapshot() -> BiscuitSnapshot {
+        let mut snapshot = BiscuitSnapshot::create_with_name("round-trip-test");
+        snapshot.add_package_info(
+            "pacman",
+            "6.0.1-1",
+            InstallReason::Explicit,
+            Origin::Repo("core".to_string()),
+        );
+        snapshot.add_package_info("yay", "12.0.0-1", InstallReason::Explicit, Origin::Foreign);
+        snapshot
+    }
+
+    fn assert_round_trips(format: Format, extension: &str) {
+        let path = std::env::temp_dir().join(format!(
+            "biscuit-test-{}-{}.{}",
+            std::process::id(),
+            extension,
+            extension
+        ));
+        let _ = fs::remove_file(&path);
+        let path_str = path.to_str().unwrap();
+
+        let snapshot = sample_snapshot();
+        snapshot.save_to_file(path_str, format).unwrap();
+        let loaded = BiscuitSnapshot::load_from_file(path_str).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.package_infos[0].name, "pacman");
+        assert_eq!(loaded.package_infos[0].origin, Origin::Repo("core".to_string()));
+        assert_eq!(loaded.package_infos[1].name, "yay");
+        assert_eq!(loaded.package_infos[1].origin, Origin::Foreign);
+    }
+
+    #[test]
+    fn toml_round_trip_preserves_origin() {
+        assert_round_trips(Format::Toml, "toml");
+    }
+
+    #[test]
+    fn json_round_trip_preserves_origin() {
+        assert_round_trips(Format::Json, "json");
+    }
+}
+
+pub fn run(launch_name: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut opts = Options::new();
+    cli::add_common_opts(&mut opts);
+    cli::add_db_opt(&mut opts);
+    opts.optopt("n", "name", "[Required] the name of the snapshot", "NAME");
+    opts.optopt("o", "output", "the output filename (default = \"NAME.<format>\")", "FILE");
+    opts.optopt("", "format", "the file format to save as: \"toml\" or \"json\" (default = \"toml\")", "FORMAT");
+
+    let matches = opts.parse(args).map_err(|f| {
+        cli::show_usage(launch_name, "snapshot", &opts);
+        f
+    })?;
+
+    if matches.opt_present("h") {
+        cli::show_usage(launch_name, "snapshot", &opts);
+        return Ok(());
+    }
+
+    if !matches.opt_present("n") {
+        cli::show_usage(launch_name, "snapshot", &opts);
+        return Err("Missing required argument: name".into());
+    }
+
+    let name = matches.opt_str("n").unwrap();
+    let format = Format::parse(&matches.opt_str("format").unwrap_or(String::from("toml")))?;
+    let output_filename = matches
+        .opt_str("o")
+        .unwrap_or(format!("{}.{}", name, format.extension()));
+    let root_path = cli::root_path(&matches);
+    let database_path = cli::database_path(&matches);
+
+    let mut snapshot = BiscuitSnapshot::create_with_name(&name);
+    write_to_snapshot(&mut snapshot, &root_path, &database_path)?;
+
+    match cli::db_path(&matches) {
+        Some(db_path) => db::save_to_db(&snapshot, &db_path)?,
+        None => snapshot.save_to_file(&output_filename, format)?,
+    }
+
+    Ok(())
+}